@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+use binary_parser::Binary;
+
+use crate::{Bom, BomError, TreeEntry};
+
+/// The pieces of a decoded `TreeEntry` that `reduce_tree` and
+/// [`crate::BomTreeCursor`] need, plus (for interior nodes) the resolved
+/// child pointer index that would otherwise require a second seek.
+#[derive(Clone, Copy)]
+pub(crate) struct CachedNode {
+    pub is_leaf: bool,
+    pub count: u16,
+    pub forward: u32,
+    #[allow(dead_code)]
+    pub backward: u32,
+    pub child: Option<u32>,
+}
+
+/// Shared cache of decoded tree blocks, keyed by block address.
+///
+/// Cloning a `NodeReader` is cheap (it just bumps a refcount), so `Bom` and
+/// every `BomTreeCursor` borrowed from it consult the same cache instead of
+/// each re-parsing and re-bounds-checking the blocks a tree traversal
+/// revisits.
+#[derive(Clone)]
+pub(crate) struct NodeReader {
+    cache: Arc<Mutex<HashMap<u32, CachedNode>>>,
+}
+
+impl NodeReader {
+    pub fn new() -> Self {
+        NodeReader { cache: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Drops every cached node. Must be called after anything rewrites the
+    /// blocks at existing addresses or reassigns a tree's child pointer.
+    pub fn invalidate(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Returns the decoded node at `pointer_index`, parsing and caching it
+    /// on a miss.
+    pub fn get<B: AsRef<[u8]>>(&self, bom: &Bom<B>, pointer_index: u32) -> Result<CachedNode, BomError> {
+        let pointer = bom.pointer(pointer_index).ok_or(BomError::InvalidPointerIndex(pointer_index))?;
+
+        if let Some(node) = self.cache.lock().unwrap().get(&pointer.address) {
+            return Ok(*node);
+        }
+
+        let mut bin = Binary::new(bom.buffer.as_ref());
+        bin.seek(pointer.address as usize);
+        let entry = TreeEntry::try_from(
+            bin.parse_buffer(12).map_err(|_| BomError::UnexpectedEof { offset: pointer.address as usize, needed: 12 })?,
+        )?;
+
+        // Interior nodes have no entries of their own; their single child's
+        // pointer index is stored right after the node's own block.
+        let child = if entry.is_leaf == 0 && entry.count == 0 {
+            let offset = (pointer.address + pointer.length) as usize;
+            bin.seek(offset);
+            Some(bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset, needed: 4 })?)
+        } else {
+            None
+        };
+
+        let node = CachedNode {
+            is_leaf: entry.is_leaf > 0,
+            count: entry.count,
+            forward: entry.forward,
+            backward: entry.backward,
+            child,
+        };
+        self.cache.lock().unwrap().insert(pointer.address, node);
+        Ok(node)
+    }
+}
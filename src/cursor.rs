@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ops::{Bound, RangeBounds};
+
+use binary_parser::Binary;
+
+use crate::{Bom, BomError, Tree, TreeEntryIndices};
+
+/// Lazily walks the leaves of a BOM B-tree, yielding `(key, value)` pairs in
+/// sorted order without materializing the whole tree up front.
+pub struct BomTreeCursor<'b, B: AsRef<[u8]> = Vec<u8>> {
+    bom: &'b Bom<B>,
+    // Pointer index of the leaf `TreeEntry` currently being read, or `None`
+    // once iteration has run past the last leaf.
+    leaf: Option<u32>,
+    // Index of the next entry to yield within the current leaf.
+    index: u16,
+    // (start key, exclude entries equal to it). Re-checked on every entry
+    // (not just the leaf `descend` lands on) since entries of a later leaf in
+    // the chain may still sort before the start too.
+    start: Option<(Vec<u8>, bool)>,
+    end: Bound<Vec<u8>>,
+    // `None` for a plain `new()` cursor, which has no bounds to compare
+    // against and so never needs an ordering.
+    cmp: Option<Box<dyn Fn(&[u8], &[u8]) -> Ordering + 'b>>,
+}
+
+impl<'b, B: AsRef<[u8]>> BomTreeCursor<'b, B> {
+    /// Walks every entry reachable from `pointer_index`, in sorted order.
+    pub fn new(bom: &'b Bom<B>, pointer_index: u32) -> Self {
+        let (leaf, index) = descend(bom, pointer_index, None);
+        BomTreeCursor { bom, leaf, index, start: None, end: Bound::Unbounded, cmp: None }
+    }
+
+    /// Walks only the entries of `var`'s tree whose key falls within `bounds`,
+    /// using `cmp` to order keys.
+    ///
+    /// `cmp(a, b)` must return `a`'s `Ordering` relative to `b`, following the
+    /// same convention as `Ord::cmp`. It's used both to binary-search the
+    /// start of the range and to decide when the end of the range has been
+    /// reached, so a non-lexicographic order is honored consistently on both
+    /// ends. Since BOM keys are not always simple byte strings (for example
+    /// `Paths` keys are a `parent: u32` followed by a name), `cmp` lets
+    /// callers order by whatever the tree is actually sorted under — e.g.
+    /// comparing only the name suffix for a prefix lookup within a known
+    /// parent. Pass `|a, b| a.cmp(b)` to reproduce a plain lexicographic
+    /// range.
+    pub fn range<'r, Bounds, Cmp>(bom: &'b Bom<B>, var: &str, bounds: Bounds, cmp: Cmp) -> Result<Self, BomError>
+    where
+        Bounds: RangeBounds<&'r [u8]>,
+        Cmp: Fn(&[u8], &[u8]) -> Ordering + 'b,
+    {
+        let pointer = bom.pointer_for_var(var).ok_or_else(|| BomError::VariableNotFound(var.to_string()))?;
+        let bytes = bom.buffer.as_ref();
+        let buf = bytes
+            .get(pointer.address as usize..)
+            .ok_or(BomError::OffsetOutOfBounds { offset: pointer.address as usize, len: bytes.len() })?;
+        let tree = Tree::try_from(buf)?;
+
+        let cmp: Box<dyn Fn(&[u8], &[u8]) -> Ordering + 'b> = Box::new(cmp);
+
+        let start = match bounds.start_bound() {
+            Bound::Included(k) => Some((k.to_vec(), false)),
+            Bound::Excluded(k) => Some((k.to_vec(), true)),
+            Bound::Unbounded => None,
+        };
+
+        let (leaf, index) = match &start {
+            Some((key, _)) => descend(bom, tree.child, Some(&|candidate: &[u8]| cmp(candidate, key))),
+            None => descend(bom, tree.child, None),
+        };
+
+        let end = match bounds.end_bound() {
+            Bound::Included(k) => Bound::Included(k.to_vec()),
+            Bound::Excluded(k) => Bound::Excluded(k.to_vec()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        Ok(BomTreeCursor { bom, leaf, index, start, end, cmp: Some(cmp) })
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        match &self.cmp {
+            Some(cmp) => cmp(a, b),
+            None => a.cmp(b),
+        }
+    }
+}
+
+impl<'b, B: AsRef<[u8]>> Iterator for BomTreeCursor<'b, B> {
+    type Item = (&'b [u8], &'b [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let leaf_index = self.leaf?;
+
+            if self.index >= entry_count(self.bom, leaf_index) {
+                let (next_leaf, next_index) = advance(self.bom, leaf_index);
+                self.leaf = next_leaf;
+                self.index = next_index;
+                continue;
+            }
+
+            let (key, value) = entry_at(self.bom, leaf_index, self.index)?;
+
+            // `descend` only binary-searches the leaf it lands on; re-apply
+            // the start bound to every entry so crossing into a later leaf
+            // (via `advance`) can't yield anything before it.
+            let before_start = match &self.start {
+                Some((start, exclude_equal)) => match self.compare(key, start) {
+                    Ordering::Less => true,
+                    Ordering::Equal => *exclude_equal,
+                    Ordering::Greater => false,
+                },
+                None => false,
+            };
+            if before_start {
+                self.index += 1;
+                continue;
+            }
+
+            let past_end = match &self.end {
+                Bound::Included(end) => self.compare(key, end) == Ordering::Greater,
+                Bound::Excluded(end) => self.compare(key, end) != Ordering::Less,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                self.leaf = None;
+                return None;
+            }
+
+            self.index += 1;
+            return Some((key, value));
+        }
+    }
+}
+
+/// Descends from `pointer_index` along interior nodes' child pointers to the
+/// first leaf, returning its pointer index and the index of the first entry
+/// in that leaf `cmp` doesn't order before the start (0 if `cmp` is `None`,
+/// i.e. unbounded). Later leaves are not searched; the start bound is
+/// applied to their entries in `next`.
+fn descend<B: AsRef<[u8]>>(bom: &Bom<B>, mut pointer_index: u32, cmp: Option<&dyn Fn(&[u8]) -> Ordering>) -> (Option<u32>, u16) {
+    loop {
+        let node = match bom.node_reader.get(bom, pointer_index) {
+            Ok(node) => node,
+            Err(_) => return (None, 0),
+        };
+
+        if node.is_leaf {
+            let index = match cmp {
+                Some(cmp) => lower_bound(bom, pointer_index, node.count, cmp),
+                None => 0,
+            };
+            return (Some(pointer_index), index);
+        }
+
+        pointer_index = match node.child {
+            Some(child) => child,
+            None => return (None, 0),
+        };
+    }
+}
+
+/// Binary-searches the `count` `TreeEntryIndices` of leaf `leaf_index` for
+/// the first entry `cmp` doesn't order before the target, following the
+/// convention of [`slice::binary_search_by`].
+fn lower_bound<B: AsRef<[u8]>>(bom: &Bom<B>, leaf_index: u32, count: u16, cmp: &dyn Fn(&[u8]) -> Ordering) -> u16 {
+    let mut lo = 0u16;
+    let mut hi = count;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match entry_key(bom, leaf_index, mid) {
+            Some(mid_key) if cmp(&mid_key) == Ordering::Less => lo = mid + 1,
+            _ => hi = mid,
+        }
+    }
+    lo
+}
+
+fn entry_count<B: AsRef<[u8]>>(bom: &Bom<B>, leaf_index: u32) -> u16 {
+    bom.node_reader.get(bom, leaf_index).map(|node| node.count).unwrap_or(0)
+}
+
+/// Moves to the next leaf in chain order (the `forward` sibling), skipping
+/// over empty leaves, and returns the index of its first entry.
+fn advance<B: AsRef<[u8]>>(bom: &Bom<B>, leaf_index: u32) -> (Option<u32>, u16) {
+    let node = match bom.node_reader.get(bom, leaf_index) {
+        Ok(node) => node,
+        Err(_) => return (None, 0),
+    };
+    if node.forward == 0 {
+        return (None, 0);
+    }
+    if entry_count(bom, node.forward) == 0 {
+        return advance(bom, node.forward);
+    }
+    (Some(node.forward), 0)
+}
+
+fn entry_at<B: AsRef<[u8]>>(bom: &Bom<B>, leaf_index: u32, index: u16) -> Option<(&[u8], &[u8])> {
+    let pointer = bom.pointer(leaf_index)?;
+    let bin = Binary::new(bom.buffer.as_ref());
+    let indices = TreeEntryIndices::try_from(bin.get_buffer(pointer.address as usize + 12 + index as usize * 8, 8).ok()?).ok()?;
+    let key_ptr = bom.pointer(indices.key_index)?;
+    let value_ptr = bom.pointer(indices.value_index)?;
+    Some((
+        bin.get_buffer(key_ptr.address as usize, key_ptr.length as usize).ok()?,
+        bin.get_buffer(value_ptr.address as usize, value_ptr.length as usize).ok()?,
+    ))
+}
+
+fn entry_key<B: AsRef<[u8]>>(bom: &Bom<B>, leaf_index: u32, index: u16) -> Option<Vec<u8>> {
+    entry_at(bom, leaf_index, index).map(|(key, _)| key.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use crate::BomBuilder;
+
+    // `max_leaf_entries(DEFAULT_BLOCK_SIZE)` is 510, so 1200 entries spans
+    // three leaves and the start of the range below lands in the middle of
+    // the second one.
+    const ENTRY_COUNT: u32 = 1200;
+
+    fn numbers_bom() -> crate::Bom<Vec<u8>> {
+        let mut builder = BomBuilder::new();
+        for i in 0..ENTRY_COUNT {
+            builder = builder.insert("Numbers", i.to_be_bytes().to_vec(), i.to_be_bytes().to_vec());
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn range_filters_start_bound_across_leaf_boundaries() {
+        let bom = numbers_bom();
+        let start = 600u32.to_be_bytes();
+        let end = 900u32.to_be_bytes();
+
+        let entries: Vec<u32> = bom
+            .range("Numbers", start.as_slice()..end.as_slice(), |a, b| a.cmp(b))
+            .unwrap()
+            .map(|(key, _)| u32::from_be_bytes(key.try_into().unwrap()))
+            .collect();
+
+        let expected: Vec<u32> = (600..900).collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn range_unbounded_visits_every_entry() {
+        let bom = numbers_bom();
+        let count = bom.range("Numbers", .., |a, b| a.cmp(b)).unwrap().count();
+        assert_eq!(count, ENTRY_COUNT as usize);
+    }
+
+    #[test]
+    fn range_excluded_start_skips_the_boundary_key() {
+        let bom = numbers_bom();
+        let start = 10u32.to_be_bytes();
+        let first = bom
+            .range("Numbers", (Bound::Excluded(start.as_slice()), Bound::Unbounded), |a, b| a.cmp(b))
+            .unwrap()
+            .next()
+            .map(|(key, _)| u32::from_be_bytes(key.try_into().unwrap()));
+        assert_eq!(first, Some(11));
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while parsing a BOM/CAR file.
+#[derive(Debug)]
+pub enum BomError {
+    /// The file doesn't start with the `BOMStore` signature.
+    BadSignature,
+    /// A read would have gone past the end of the buffer.
+    UnexpectedEof { offset: usize, needed: usize },
+    /// A stored offset points outside of the buffer.
+    OffsetOutOfBounds { offset: usize, len: usize },
+    /// A tree node reference (a tree's `child`, an interior node's child or a
+    /// leaf's `forward` link) named a pointer that doesn't exist.
+    InvalidPointerIndex(u32),
+    /// `reduce_tree_for_variable`/`map_tree_for_variable` was asked for a variable
+    /// that isn't in the variables table.
+    VariableNotFound(String),
+    /// The catalog parsed but violates an invariant callers rely on, e.g. a
+    /// `Paths` tree with no root entry.
+    Corrupt(String),
+    /// Reading the underlying file failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for BomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BomError::BadSignature => write!(f, "not a BOM file: bad signature"),
+            BomError::UnexpectedEof { offset, needed } => {
+                write!(f, "unexpected end of file: needed {} bytes at offset {}", needed, offset)
+            }
+            BomError::OffsetOutOfBounds { offset, len } => {
+                write!(f, "offset {} is out of bounds for a buffer of length {}", offset, len)
+            }
+            BomError::InvalidPointerIndex(index) => write!(f, "invalid pointer index: {}", index),
+            BomError::VariableNotFound(name) => write!(f, "variable not found: {}", name),
+            BomError::Corrupt(message) => write!(f, "corrupt BOM file: {}", message),
+            BomError::Io(err) => write!(f, "failed to read BOM file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BomError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BomError {
+    fn from(err: io::Error) -> Self {
+        BomError::Io(err)
+    }
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "explorer")]
+
+//! A read-only HTTP view of a loaded [`Bom`], behind the `explorer` feature
+//! so the core crate stays dependency-light. Start it with [`Bom::serve`].
+
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use binary_parser::Binary;
+use log::warn;
+
+use crate::{util, Bom, BomError, Tree, TreeEntryIndices};
+
+impl<B: AsRef<[u8]>> Bom<B> {
+    /// Serves a browsable HTML view of this catalog over HTTP, blocking the
+    /// calling thread until the listener errors.
+    ///
+    /// Requires the `explorer` feature.
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            if let Err(err) = self.handle(&mut stream) {
+                warn!("explorer: failed to serve request: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        let body = match segments.as_slice() {
+            [""] => self.render_index(),
+            ["var", name] => self.render_variable(name),
+            ["pointer", index] => match index.parse() {
+                Ok(index) => self.render_pointer(index),
+                Err(_) => not_found(),
+            },
+            _ => not_found(),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    fn render_index(&self) -> String {
+        let mut html = String::from("<h1>rbom explorer</h1><h2>Header</h2><ul>");
+        let _ = write!(html, "<li>version: {}</li>", self.header.version);
+        let _ = write!(html, "<li>number_of_blocks: {}</li>", self.header.number_of_blocks);
+        let _ = write!(html, "<li>index_offset: {}</li>", self.header.index_offset);
+        let _ = write!(html, "<li>index_length: {}</li>", self.header.index_length);
+        let _ = write!(html, "<li>vars_offset: {}</li>", self.header.vars_offset);
+        let _ = write!(html, "<li>vars_length: {}</li>", self.header.vars_length);
+        html.push_str("</ul><h2>Variables</h2><ul>");
+        let mut names: Vec<&String> = self.variables.keys().collect();
+        names.sort();
+        for name in names {
+            let _ = write!(html, "<li><a href=\"/var/{name}\">{name}</a></li>", name = escape_html(name));
+        }
+        html.push_str("</ul>");
+        html
+    }
+
+    fn render_variable(&self, name: &str) -> String {
+        let pointer = match self.pointer_for_var(name) {
+            Some(pointer) => pointer,
+            None => return not_found(),
+        };
+        let tree = match Bom::<B>::slice_from(self.buffer.as_ref(), pointer.address as usize).and_then(Tree::try_from) {
+            Ok(tree) => tree,
+            Err(err) => return format!("<h1>Error</h1><p>{}</p>", escape_html(&err.to_string())),
+        };
+
+        let mut indices = Vec::new();
+        if let Err(err) = self.collect_indices(tree.child, &mut indices) {
+            return format!("<h1>Error</h1><p>{}</p>", escape_html(&err.to_string()));
+        }
+
+        let mut html = format!("<h1>{}</h1><table border=\"1\"><tr><th>Key</th><th>Value</th></tr>", escape_html(name));
+        for (key_index, value_index) in indices {
+            let _ = write!(
+                html,
+                "<tr><td><a href=\"/pointer/{key_index}\">{key_index}</a>: {key}</td><td><a href=\"/pointer/{value_index}\">{value_index}</a>: {value}</td></tr>",
+                key_index = key_index,
+                value_index = value_index,
+                key = self.render_pointer_preview(key_index),
+                value = self.render_pointer_preview(value_index),
+            );
+        }
+        html.push_str("</table>");
+        html
+    }
+
+    fn render_pointer(&self, index: u32) -> String {
+        let pointer = match self.pointer(index) {
+            Some(pointer) => pointer,
+            None => return not_found(),
+        };
+        let bytes = match self.buffer.as_ref().get(pointer.address as usize..(pointer.address + pointer.length) as usize) {
+            Some(bytes) => bytes,
+            None => return not_found(),
+        };
+        format!(
+            "<h1>Pointer {}</h1><p>hex: {}</p><p>utf8: {}</p>",
+            index,
+            escape_html(&util::format_hex(bytes)),
+            escape_html(&String::from_utf8_lossy(bytes))
+        )
+    }
+
+    fn render_pointer_preview(&self, index: u32) -> String {
+        match self.pointer(index).and_then(|pointer| self.buffer.as_ref().get(pointer.address as usize..(pointer.address + pointer.length) as usize)) {
+            Some(bytes) => escape_html(&String::from_utf8_lossy(bytes)),
+            None => "?".to_string(),
+        }
+    }
+
+    /// Walks `pointer_index`'s tree collecting every leaf's `(key_index,
+    /// value_index)` pair, in place of `reduce_tree`'s decoded bytes, so the
+    /// explorer can link each entry back to its raw pointer.
+    fn collect_indices(&self, pointer_index: u32, acc: &mut Vec<(u32, u32)>) -> Result<(), BomError> {
+        let pointer = self.pointer(pointer_index).ok_or(BomError::InvalidPointerIndex(pointer_index))?;
+        let node = self.node_reader.get(self, pointer_index)?;
+        let bin = Binary::new(self.buffer.as_ref());
+
+        if node.is_leaf {
+            for i in 0..node.count {
+                let offset = pointer.address as usize + 12 + i as usize * 8;
+                let indices = TreeEntryIndices::try_from(
+                    bin.get_buffer(offset, 8).map_err(|_| BomError::UnexpectedEof { offset, needed: 8 })?,
+                )?;
+                acc.push((indices.key_index, indices.value_index));
+            }
+        } else if let Some(child) = node.child {
+            self.collect_indices(child, acc)?;
+        }
+
+        if node.forward != 0 {
+            self.collect_indices(node.forward, acc)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn not_found() -> String {
+    "<h1>404 Not Found</h1>".to_string()
+}
@@ -1,18 +1,39 @@
+mod cache;
+mod cursor;
+mod error;
+#[cfg(feature = "explorer")]
+mod explorer;
+mod tree;
 mod util;
+mod write;
+
+use cache::NodeReader;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
 use std::fs;
 use std::str;
 use binary_parser::Binary;
 use log::warn;
 
-pub struct Bom {
-    pub buffer: Vec<u8>,
+pub use cursor::BomTreeCursor;
+pub use error::BomError;
+pub use tree::{FileMetadata, Node};
+pub use write::BomBuilder;
+
+/// A parsed BOM/CAR file, generic over how its raw bytes are stored.
+///
+/// `B` is typically a `Vec<u8>` (via [`Bom::with_file`]), a borrowed `&[u8]`,
+/// or a [`memmap2::Mmap`] (via [`Bom::with_mmap`]) for opening large asset
+/// catalogs without reading them fully into RAM.
+pub struct Bom<B: AsRef<[u8]> = Vec<u8>> {
+    pub buffer: B,
     header: Header,
     pointers: Vec<Pointer>,
     free_pointers: Vec<Pointer>,
     variables: HashMap<String, u32>,
+    node_reader: NodeReader,
 }
 
 pub struct Header {
@@ -25,18 +46,28 @@ pub struct Header {
     pub vars_length: u32,
 }
 
-impl From<&[u8]> for Header {
-    fn from(buf: &[u8]) -> Self {
+impl TryFrom<&[u8]> for Header {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        const SIZE: usize = 32;
+        if buf.len() < SIZE {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: SIZE });
+        }
         let mut bin = Binary::new(buf);
-        Header {
-            signature: bin.parse_bytes().unwrap(),
-            version: bin.parse_u32_be().unwrap(),
-            number_of_blocks: bin.parse_u32_be().unwrap(),
-            index_offset: bin.parse_u32_be().unwrap(),
-            index_length: bin.parse_u32_be().unwrap(),
-            vars_offset: bin.parse_u32_be().unwrap(),
-            vars_length: bin.parse_u32_be().unwrap(),
+        let header = Header {
+            signature: bin.parse_bytes().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: SIZE })?,
+            version: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 8, needed: SIZE })?,
+            number_of_blocks: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 12, needed: SIZE })?,
+            index_offset: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 16, needed: SIZE })?,
+            index_length: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 20, needed: SIZE })?,
+            vars_offset: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 24, needed: SIZE })?,
+            vars_length: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 28, needed: SIZE })?,
+        };
+        if &header.signature != b"BOMStore" {
+            return Err(BomError::BadSignature);
         }
+        Ok(header)
     }
 }
 
@@ -45,13 +76,18 @@ pub struct Pointer {
     pub length: u32,
 }
 
-impl From<&[u8]> for Pointer {
-    fn from(buf: &[u8]) -> Self {
-        let mut bin = Binary::new(buf);
-        Pointer {
-            address: bin.parse_u32_be().unwrap(),
-            length: bin.parse_u32_be().unwrap(),
+impl TryFrom<&[u8]> for Pointer {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        if buf.len() < 8 {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: 8 });
         }
+        let mut bin = Binary::new(buf);
+        Ok(Pointer {
+            address: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: 8 })?,
+            length: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 4, needed: 8 })?,
+        })
     }
 }
 
@@ -62,16 +98,24 @@ pub struct Var {
     pub name: String,
 }
 
-impl From<&[u8]> for Var {
-    fn from(buf: &[u8]) -> Self {
+impl TryFrom<&[u8]> for Var {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        if buf.len() < 5 {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: 5 });
+        }
         let mut bin = Binary::new(buf);
-        let index = bin.parse_u32_be().unwrap();
-        let length = bin.parse_u8().unwrap();
-        Var {
+        let index = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: 5 })?;
+        let length = bin.parse_u8().map_err(|_| BomError::UnexpectedEof { offset: 4, needed: 5 })?;
+        if buf.len() < 5 + length as usize {
+            return Err(BomError::UnexpectedEof { offset: 5, needed: length as usize });
+        }
+        Ok(Var {
             index,
             length,
-            name: bin.parse_string(length as usize).unwrap(),
-        }
+            name: bin.parse_string(length as usize).map_err(|_| BomError::UnexpectedEof { offset: 5, needed: length as usize })?,
+        })
     }
 }
 
@@ -85,17 +129,23 @@ pub struct Tree {
     unknown: u8,
 }
 
-impl From<&[u8]> for Tree {
-    fn from(buf: &[u8]) -> Self {
-        let mut bin = Binary::new(buf);
-        Tree {
-            tree: bin.parse_bytes().unwrap(),
-            version: bin.parse_u32_be().unwrap(),
-            child: bin.parse_u32_be().unwrap(),
-            block_size: bin.parse_u32_be().unwrap(),
-            path_count: bin.parse_u32_be().unwrap(),
-            unknown: bin.parse_u8().unwrap(),
+impl TryFrom<&[u8]> for Tree {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        const SIZE: usize = 21;
+        if buf.len() < SIZE {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: SIZE });
         }
+        let mut bin = Binary::new(buf);
+        Ok(Tree {
+            tree: bin.parse_bytes().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: SIZE })?,
+            version: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 4, needed: SIZE })?,
+            child: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 8, needed: SIZE })?,
+            block_size: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 12, needed: SIZE })?,
+            path_count: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 16, needed: SIZE })?,
+            unknown: bin.parse_u8().map_err(|_| BomError::UnexpectedEof { offset: 20, needed: SIZE })?,
+        })
     }
 }
 
@@ -104,13 +154,18 @@ pub struct TreeEntryIndices {
     pub key_index: u32,
 }
 
-impl From<&[u8]> for TreeEntryIndices {
-    fn from(buf: &[u8]) -> Self {
-        let mut bin = Binary::new(buf);
-        TreeEntryIndices {
-            value_index: bin.parse_u32_be().unwrap(),
-            key_index: bin.parse_u32_be().unwrap(),
+impl TryFrom<&[u8]> for TreeEntryIndices {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        if buf.len() < 8 {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: 8 });
         }
+        let mut bin = Binary::new(buf);
+        Ok(TreeEntryIndices {
+            value_index: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: 8 })?,
+            key_index: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 4, needed: 8 })?,
+        })
     }
 }
 
@@ -121,60 +176,83 @@ pub struct TreeEntry {
     pub backward: u32,
 }
 
-impl From<&[u8]> for TreeEntry {
-    fn from(buf: &[u8]) -> Self {
-        let mut bin = Binary::new(buf);
-        TreeEntry {
-            is_leaf: bin.parse_u16_be().unwrap(),
-            count: bin.parse_u16_be().unwrap(),
-            forward: bin.parse_u32_be().unwrap(),
-            backward: bin.parse_u32_be().unwrap(),
+impl TryFrom<&[u8]> for TreeEntry {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        const SIZE: usize = 12;
+        if buf.len() < SIZE {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: SIZE });
         }
+        let mut bin = Binary::new(buf);
+        Ok(TreeEntry {
+            is_leaf: bin.parse_u16_be().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: SIZE })?,
+            count: bin.parse_u16_be().map_err(|_| BomError::UnexpectedEof { offset: 2, needed: SIZE })?,
+            forward: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 4, needed: SIZE })?,
+            backward: bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 8, needed: SIZE })?,
+        })
     }
 }
 
-impl Bom {
-    pub fn new(buffer: Vec<u8>) -> Self {
-        let header = Header::from(&buffer[..]);
-        let pointers = Self::parse_pointers(&buffer[header.index_offset as usize..]);
+impl<B: AsRef<[u8]>> Bom<B> {
+    pub fn new(buffer: B) -> Result<Self, BomError> {
+        let bytes = buffer.as_ref();
+        let header = Header::try_from(bytes)?;
+        let pointers = Self::parse_pointers(Self::slice_from(bytes, header.index_offset as usize)?)?;
         let free_pointers_offset = header.index_offset as usize + 4 + pointers.len() * 8;
-        let free_pointers = Self::parse_pointers(&buffer[free_pointers_offset..]);
-        let variables = Self::parse_vars(&buffer[header.vars_offset as usize..]);
-        Bom {
+        let free_pointers = Self::parse_pointers(Self::slice_from(bytes, free_pointers_offset)?)?;
+        let variables = Self::parse_vars(Self::slice_from(bytes, header.vars_offset as usize)?)?;
+        Ok(Bom {
             buffer,
             header,
             pointers,
             free_pointers,
             variables,
-        }
+            node_reader: NodeReader::new(),
+        })
     }
 
-    pub fn with_file(path: &str) -> Self {
-        Self::new(fs::read(path).unwrap())
+    /// Returns `buffer[offset..]`, or an error instead of panicking if `offset`
+    /// falls outside of `buffer`.
+    pub(crate) fn slice_from(buffer: &[u8], offset: usize) -> Result<&[u8], BomError> {
+        buffer
+            .get(offset..)
+            .ok_or(BomError::OffsetOutOfBounds { offset, len: buffer.len() })
     }
 
-    fn parse_pointers(bytes: &[u8]) -> Vec<Pointer> {
+    fn parse_pointers(bytes: &[u8]) -> Result<Vec<Pointer>, BomError> {
+        if bytes.len() < 4 {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: 4 });
+        }
         let mut bin = Binary::new(bytes);
-        let pointer_count = bin.parse_u32_be().unwrap();
+        let pointer_count = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: 4 })?;
         let mut pointers = Vec::new();
-        for _ in 0..pointer_count {
-            let block = Pointer::from(bin.parse_buffer(8).unwrap());
+        for i in 0..pointer_count {
+            let offset = 4 + i as usize * 8;
+            let block = Pointer::try_from(
+                bin.parse_buffer(8).map_err(|_| BomError::UnexpectedEof { offset, needed: 8 })?,
+            )?;
             pointers.push(block);
         }
-        pointers
+        Ok(pointers)
     }
 
-    fn parse_vars(bytes: &[u8]) -> HashMap<String, u32> {
+    fn parse_vars(bytes: &[u8]) -> Result<HashMap<String, u32>, BomError> {
+        if bytes.len() < 4 {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: 4 });
+        }
         let mut bin = Binary::new(bytes);
-        let var_count = bin.parse_u32_be().unwrap();
+        let var_count = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: 4 })?;
         let mut vars = HashMap::new();
         let mut pointer = 0;
         for _ in 0..var_count {
-            let var = Var::from(bin.get_buffer(bin.position() + pointer, 1024).unwrap());
+            let offset = bin.position() + pointer;
+            let remaining = Self::slice_from(bytes, offset)?;
+            let var = Var::try_from(remaining)?;
             pointer += var.length as usize + 5;
             vars.insert(var.name, var.index);
         }
-        vars
+        Ok(vars)
     }
 
     pub fn pointer(&self, index: u32) -> Option<&Pointer> {
@@ -182,35 +260,40 @@ impl Bom {
     }
 
     pub fn pointer_for_var(&self, name: &str) -> Option<&Pointer> {
-        self.variables.get(name).map(|index| self.pointer(*index)).flatten()
+        self.variables.get(name).and_then(|index| self.pointer(*index))
     }
 
-    pub fn reduce_tree<'b, F, R>(&'b self, pointer_index: u32, initial_value: R, reduce: F) -> R
+    pub fn reduce_tree<'b, F, R>(&'b self, pointer_index: u32, initial_value: R, reduce: F) -> Result<R, BomError>
     where
         F: Fn(R, &'b [u8], &'b [u8]) -> R + Copy,
     {
-        // Get the tree entry from the provided index
-        let pointer = &self.pointer(pointer_index).unwrap();
-        let mut bin = Binary::new(&self.buffer);
-        bin.seek(pointer.address as usize);
-        let entry = TreeEntry::from(bin.parse_buffer(12).unwrap());
+        // Get the tree entry from the provided index, via the shared cache so
+        // repeated traversals don't re-decode blocks they've already visited
+        let pointer = self.pointer(pointer_index).ok_or(BomError::InvalidPointerIndex(pointer_index))?;
+        let node = self.node_reader.get(self, pointer_index)?;
+        let bin = Binary::new(self.buffer.as_ref());
 
         // Store initial value to reduce into
         let mut current_value = initial_value;
 
-        if entry.is_leaf > 0 {
+        if node.is_leaf {
             // If it's a leaf then process the data
-            for _ in 0..entry.count {
+            for i in 0..node.count {
                 // Each leaf has multiple entries which consist of a key and value pointer
-                let indices = TreeEntryIndices::from(bin.parse_buffer(8).unwrap());
+                let offset = pointer.address as usize + 12 + i as usize * 8;
+                let indices = TreeEntryIndices::try_from(
+                    bin.get_buffer(offset, 8).map_err(|_| BomError::UnexpectedEof { offset, needed: 8 })?,
+                )?;
                 // Get both the key and value pointers and check that they exist and are not empty
                 // Corrupt files will cause out of bounds errors here otherwise
                 match (self.pointer(indices.key_index), self.pointer(indices.value_index)) {
                     (Some(key_ptr), Some(value_ptr)) if key_ptr.length > 0 && value_ptr.length > 0 => {
                         current_value = reduce(
                             current_value,
-                            bin.get_buffer(key_ptr.address as usize, key_ptr.length as usize).unwrap(),
-                            bin.get_buffer(value_ptr.address as usize, value_ptr.length as usize).unwrap(),
+                            bin.get_buffer(key_ptr.address as usize, key_ptr.length as usize)
+                                .map_err(|_| BomError::OffsetOutOfBounds { offset: key_ptr.address as usize, len: self.buffer.as_ref().len() })?,
+                            bin.get_buffer(value_ptr.address as usize, value_ptr.length as usize)
+                                .map_err(|_| BomError::OffsetOutOfBounds { offset: value_ptr.address as usize, len: self.buffer.as_ref().len() })?,
                         );
                     }
                     _ => {
@@ -220,41 +303,37 @@ impl Bom {
                     }
                 }
             }
-        } else if entry.count == 0 {
+        } else if node.count == 0 {
             // The tree entry that's not a leaf should have no entries
             // TODO: Is this true though? Tere's a case of a weird asset catalog that has a count of more
             // but if trying to parse it will throw an exception
             // If not a leaf then get index of child pointer
-            bin.seek((pointer.address + pointer.length) as usize); // TODO: maybe not needed?
-            let index = bin.parse_u32_be().unwrap();
-            current_value = self.reduce_tree(index, current_value, reduce);
+            if let Some(child) = node.child {
+                current_value = self.reduce_tree(child, current_value, reduce)?;
+            }
         } else {
-            warn!("Encountered a tree entry that's not a leaf and has entries (count: {})", entry.count);
+            warn!("Encountered a tree entry that's not a leaf and has entries (count: {})", node.count);
         }
 
         // If has siblings then move horizontally to the next sibling
-        if entry.forward != 0 {
-            current_value = self.reduce_tree(entry.forward, current_value, reduce);
+        if node.forward != 0 {
+            current_value = self.reduce_tree(node.forward, current_value, reduce)?;
         }
 
         // Return accumulated value
-        current_value
+        Ok(current_value)
     }
 
-    pub fn reduce_tree_for_variable<'b, F, R>(&'b self, var: &str, initial_value: R, reduce: F) -> Result<R, String>
+    pub fn reduce_tree_for_variable<'b, F, R>(&'b self, var: &str, initial_value: R, reduce: F) -> Result<R, BomError>
     where
         F: Fn(R, &'b [u8], &'b [u8]) -> R + Copy,
     {
-        match self.pointer_for_var(var) {
-            Some(pointer) => {
-                let tree = Tree::from(&self.buffer[pointer.address as usize..]);
-                Ok(self.reduce_tree(tree.child, initial_value, reduce))
-            }
-            None => Err(format!("Variable not found: {}", var)),
-        }
+        let pointer = self.pointer_for_var(var).ok_or_else(|| BomError::VariableNotFound(var.to_string()))?;
+        let tree = Tree::try_from(Self::slice_from(self.buffer.as_ref(), pointer.address as usize)?)?;
+        self.reduce_tree(tree.child, initial_value, reduce)
     }
 
-    pub fn map_tree<'b, F, V>(&'b self, pointer_index: u32, map: F) -> Vec<V>
+    pub fn map_tree<'b, F, V>(&'b self, pointer_index: u32, map: F) -> Result<Vec<V>, BomError>
     where
         F: Fn(&'b [u8], &'b [u8]) -> V + Copy,
     {
@@ -264,17 +343,61 @@ impl Bom {
         })
     }
 
-    pub fn map_tree_for_variable<'b, F, V>(&'b self, var: &str, map: F) -> Vec<V>
+    pub fn map_tree_for_variable<'b, F, V>(&'b self, var: &str, map: F) -> Result<Vec<V>, BomError>
     where
         F: Fn(&'b [u8], &'b [u8]) -> V + Copy,
     {
-        let pointer = self.pointer_for_var(var).unwrap();
-        let tree = Tree::from(&self.buffer[pointer.address as usize..]);
+        let pointer = self.pointer_for_var(var).ok_or_else(|| BomError::VariableNotFound(var.to_string()))?;
+        let tree = Tree::try_from(Self::slice_from(self.buffer.as_ref(), pointer.address as usize)?)?;
         self.map_tree(tree.child, map)
     }
+
+    /// Returns a lazy cursor over every entry reachable from `pointer_index`.
+    pub fn cursor(&self, pointer_index: u32) -> BomTreeCursor<'_, B> {
+        BomTreeCursor::new(self, pointer_index)
+    }
+
+    /// Returns a lazy cursor over the entries of `var`'s tree whose key falls
+    /// within `bounds`. Iteration stops at the first key past the end of the
+    /// range; entries before the start that lie beyond the first leaf are
+    /// still read and skipped one by one.
+    ///
+    /// `cmp(a, b)` orders keys the same way `Ord::cmp` would, and is used
+    /// both to binary-search the start of the range and to decide where it
+    /// ends; see [`BomTreeCursor::range`] for its contract. Passing
+    /// `|a, b| a.cmp(b)` reproduces a plain lexicographic range.
+    pub fn range<'b, 'r, Bounds, Cmp>(&'b self, var: &str, bounds: Bounds, cmp: Cmp) -> Result<BomTreeCursor<'b, B>, BomError>
+    where
+        Bounds: std::ops::RangeBounds<&'r [u8]>,
+        Cmp: Fn(&[u8], &[u8]) -> std::cmp::Ordering + 'b,
+    {
+        BomTreeCursor::range(self, var, bounds, cmp)
+    }
+
+    /// Parses the `Paths` variable into an in-memory directory tree, resolving
+    /// the `parent`/`id` relationships between entries.
+    pub fn file_tree(&self) -> Result<Node, BomError> {
+        tree::file_tree(self)
+    }
+}
+
+impl Bom<Vec<u8>> {
+    pub fn with_file(path: &str) -> Result<Self, BomError> {
+        Self::new(fs::read(path)?)
+    }
+}
+
+impl Bom<memmap2::Mmap> {
+    /// Memory-maps `path` read-only instead of reading it fully into RAM,
+    /// which keeps resident memory near-zero for huge asset catalogs.
+    pub fn with_mmap(path: &str) -> Result<Self, BomError> {
+        let file = fs::File::open(path)?;
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Self::new(mmap)
+    }
 }
 
-impl fmt::Debug for Bom {
+impl<B: AsRef<[u8]>> fmt::Debug for Bom<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Bom")
             .field("header", &self.header)
@@ -314,14 +437,13 @@ mod tests {
     use super::*;
 
     #[test]
-    #[should_panic]
     fn load_with_error() {
-        Bom::with_file("test_files/bleh.car");
+        assert!(Bom::with_file("test_files/bleh.car").is_err());
     }
 
     #[test]
     fn parsing_car() {
-        let car = Bom::with_file("test_files/assets.car");
+        let car = Bom::with_file("test_files/assets.car").unwrap();
         assert_eq!(car.header.signature, [66, 79, 77, 83, 116, 111, 114, 101]);
         assert_eq!(car.header.version, 1);
         assert_eq!(car.header.number_of_blocks, 62);
@@ -343,7 +465,7 @@ mod tests {
 
     #[test]
     fn parsing_bom() {
-        let bom = Bom::with_file("test_files/test.bom");
+        let bom = Bom::with_file("test_files/test.bom").unwrap();
         assert_eq!(bom.header.signature, [66, 79, 77, 83, 116, 111, 114, 101]);
         assert_eq!(bom.header.version, 1);
         assert_eq!(bom.header.number_of_blocks, 28);
@@ -357,7 +479,7 @@ mod tests {
 
    #[test]
    fn parsing_variables() {
-         let bom = Bom::with_file("test_files/test.bom");
+         let bom = Bom::with_file("test_files/test.bom").unwrap();
          let variables = bom.variables;
          assert_eq!(variables.len(), 5);
          assert_eq!(variables.get("Size64"), Some(&9));
@@ -365,23 +487,23 @@ mod tests {
          assert_eq!(variables.get("Paths"), Some(&2));
          assert_eq!(variables.get("BomInfo"), Some(&1));
          assert_eq!(variables.get("HLIndex"), Some(&4));
-   } 
+   }
 
     #[test]
     fn reducing_tree() {
-        let bom = Bom::with_file("test_files/test2.bom");
+        let bom = Bom::with_file("test_files/test2.bom").unwrap();
         let pointer = bom.pointer_for_var("Paths").unwrap();
-        let tree = Tree::from(&bom.buffer[pointer.address as usize..]);
-        let result = bom.reduce_tree(tree.child, 0, |reduction, _, _| reduction + 1);
+        let tree = Tree::try_from(&bom.buffer[pointer.address as usize..]).unwrap();
+        let result = bom.reduce_tree(tree.child, 0, |reduction, _, _| reduction + 1).unwrap();
         assert_eq!(result, 25);
     }
 
     #[test]
     fn mapping_tree() {
-        let bom = Bom::with_file("test_files/test2.bom");
+        let bom = Bom::with_file("test_files/test2.bom").unwrap();
         let pointer = bom.pointer_for_var("Paths").unwrap();
-        let tree = Tree::from(&bom.buffer[pointer.address as usize..]);
-        let result = bom.map_tree(tree.child, |_, _| "test".to_string());
+        let tree = Tree::try_from(&bom.buffer[pointer.address as usize..]).unwrap();
+        let result = bom.map_tree(tree.child, |_, _| "test".to_string()).unwrap();
         assert_eq!(result.len(), 25);
         assert_eq!(result[0], "test".to_string());
     }
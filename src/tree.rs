@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use binary_parser::Binary;
+use log::warn;
+
+use crate::{Bom, BomError};
+
+/// Per-file attributes stored alongside each `Paths` entry.
+#[derive(Debug, Clone)]
+pub struct FileMetadata {
+    pub kind: u8,
+    pub architecture: u16,
+    pub mode: u16,
+    pub user: u32,
+    pub group: u32,
+    pub modtime: u32,
+    pub size: u32,
+    pub checksum: u32,
+}
+
+impl TryFrom<&[u8]> for FileMetadata {
+    type Error = BomError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, BomError> {
+        const SIZE: usize = 27;
+        if buf.len() < SIZE {
+            return Err(BomError::UnexpectedEof { offset: 0, needed: SIZE });
+        }
+        let mut bin = Binary::new(buf);
+        let kind = bin.parse_u8().map_err(|_| BomError::UnexpectedEof { offset: 0, needed: SIZE })?;
+        bin.skip(1);
+        let architecture = bin.parse_u16_be().map_err(|_| BomError::UnexpectedEof { offset: 2, needed: SIZE })?;
+        let mode = bin.parse_u16_be().map_err(|_| BomError::UnexpectedEof { offset: 4, needed: SIZE })?;
+        let user = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 6, needed: SIZE })?;
+        let group = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 10, needed: SIZE })?;
+        let modtime = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 14, needed: SIZE })?;
+        let size = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 18, needed: SIZE })?;
+        bin.skip(1);
+        let checksum = bin.parse_u32_be().map_err(|_| BomError::UnexpectedEof { offset: 23, needed: SIZE })?;
+        Ok(FileMetadata { kind, architecture, mode, user, group, modtime, size, checksum })
+    }
+}
+
+/// A node in the hierarchical view of a BOM's `Paths` variable.
+#[derive(Debug)]
+pub enum Node {
+    Directory { name: String, children: HashMap<String, Node> },
+    File { name: String, metadata: FileMetadata },
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Directory { name, .. } => name,
+            Node::File { name, .. } => name,
+        }
+    }
+
+    /// Looks up a `/`-separated path relative to this node.
+    pub fn resolve(&self, path: &str) -> Option<&Node> {
+        let mut node = self;
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            match node {
+                Node::Directory { children, .. } => node = children.get(part)?,
+                Node::File { .. } => return None,
+            }
+        }
+        Some(node)
+    }
+
+    /// Depth-first iterator over this node and all of its descendants,
+    /// yielding each one alongside its full path relative to this node.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { stack: vec![(String::new(), self)] }
+    }
+
+    /// The size of a file, or the sum of the sizes of everything under a
+    /// directory.
+    pub fn size(&self) -> u64 {
+        match self {
+            Node::File { metadata, .. } => metadata.size as u64,
+            Node::Directory { children, .. } => children.values().map(Node::size).sum(),
+        }
+    }
+}
+
+pub struct Iter<'n> {
+    stack: Vec<(String, &'n Node)>,
+}
+
+impl<'n> Iterator for Iter<'n> {
+    type Item = (String, &'n Node);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = self.stack.pop()?;
+        if let Node::Directory { children, .. } = node {
+            for (name, child) in children {
+                let child_path = if path.is_empty() { name.clone() } else { format!("{}/{}", path, name) };
+                self.stack.push((child_path, child));
+            }
+        }
+        Some((path, node))
+    }
+}
+
+struct Entry {
+    parent: u32,
+    name: String,
+    metadata: FileMetadata,
+}
+
+/// Builds the hierarchical [`Node`] tree described by a BOM's `Paths`
+/// variable, resolving the `parent`/`id` relationships between entries.
+pub fn file_tree<B: AsRef<[u8]>>(bom: &Bom<B>) -> Result<Node, BomError> {
+    let entries = bom.reduce_tree_for_variable("Paths", HashMap::new(), |mut acc: HashMap<u32, Entry>, key, value| {
+        if key.len() < 4 || value.len() < 8 {
+            warn!("Malformed Paths entry: key or value too short");
+            return acc;
+        }
+        let parent = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+        let name = String::from_utf8_lossy(&key[4..]).trim_end_matches('\0').to_string();
+        let id = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+        let index = u32::from_be_bytes([value[4], value[5], value[6], value[7]]);
+
+        match bom.pointer(index) {
+            Some(pointer) => {
+                let bytes = bom.buffer.as_ref();
+                let range = pointer.address as usize..pointer.address as usize + pointer.length as usize;
+                match bytes.get(range).and_then(|buf| FileMetadata::try_from(buf).ok()) {
+                    Some(metadata) => {
+                        acc.insert(id, Entry { parent, name, metadata });
+                    }
+                    None => warn!("Invalid file info for path {:?} with id {}", name, id),
+                }
+            }
+            None => warn!("Could not find file info for path {:?} with id {}", name, id),
+        }
+        acc
+    })?;
+
+    let mut children_of: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&id, entry) in &entries {
+        children_of.entry(entry.parent).or_default().push(id);
+    }
+
+    let root_id = entries
+        .iter()
+        .find(|(_, entry)| entry.parent == 0)
+        .map(|(&id, _)| id)
+        .ok_or_else(|| BomError::Corrupt("Paths tree has no root entry".to_string()))?;
+
+    let mut ancestors = HashSet::new();
+    ancestors.insert(root_id);
+    Ok(build_node(root_id, &entries, &children_of, &mut ancestors))
+}
+
+fn build_node(id: u32, entries: &HashMap<u32, Entry>, children_of: &HashMap<u32, Vec<u32>>, ancestors: &mut HashSet<u32>) -> Node {
+    let entry = &entries[&id];
+
+    // BOM kind 1 is a regular file; anything else (directory, symlink, ...)
+    // can have children of its own in the Paths tree.
+    if entry.metadata.kind == 1 {
+        return Node::File { name: entry.name.clone(), metadata: entry.metadata.clone() };
+    }
+
+    let mut children = HashMap::new();
+    if let Some(child_ids) = children_of.get(&id) {
+        for &child_id in child_ids {
+            if !ancestors.insert(child_id) {
+                warn!("Cycle detected in Paths tree: id {} is its own ancestor", child_id);
+                continue;
+            }
+            if let Some(child_entry) = entries.get(&child_id) {
+                children.insert(child_entry.name.clone(), build_node(child_id, entries, children_of, ancestors));
+            }
+            ancestors.remove(&child_id);
+        }
+    }
+    Node::Directory { name: entry.name.clone(), children }
+}
@@ -0,0 +1,481 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+use binary_parser::Binary;
+
+use crate::{Bom, BomError, Pointer, Tree, TreeEntryIndices};
+
+type Entries = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// `block_size` written into freshly built trees, and used to size leaves
+/// rewritten by [`Bom::set`]/[`Bom::remove`] when the original tree didn't
+/// specify one worth honoring.
+const DEFAULT_BLOCK_SIZE: u32 = 4096;
+
+fn put_u32_be(buffer: &mut [u8], offset: usize, value: u32) {
+    buffer[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Appends `bytes` as a new data block, reusing a freed block if one is
+/// already large enough, and returns the index of the `Pointer` registered
+/// for it.
+fn alloc(buffer: &mut Vec<u8>, pointers: &mut Vec<Pointer>, free_pointers: &mut Vec<Pointer>, bytes: &[u8]) -> u32 {
+    if let Some(slot) = free_pointers.iter().position(|pointer| pointer.length as usize >= bytes.len()) {
+        let pointer = free_pointers.remove(slot);
+        buffer[pointer.address as usize..pointer.address as usize + bytes.len()].copy_from_slice(bytes);
+        pointers.push(Pointer { address: pointer.address, length: bytes.len() as u32 });
+    } else {
+        let address = buffer.len() as u32;
+        buffer.extend_from_slice(bytes);
+        pointers.push(Pointer { address, length: bytes.len() as u32 });
+    }
+    (pointers.len() - 1) as u32
+}
+
+fn leaf_bytes(indices: &[(u32, u32)]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(12 + indices.len() * 8);
+    bytes.extend_from_slice(&1u16.to_be_bytes()); // is_leaf
+    bytes.extend_from_slice(&(indices.len() as u16).to_be_bytes()); // count
+    bytes.extend_from_slice(&0u32.to_be_bytes()); // forward
+    bytes.extend_from_slice(&0u32.to_be_bytes()); // backward
+    for (value_index, key_index) in indices {
+        bytes.extend_from_slice(&value_index.to_be_bytes());
+        bytes.extend_from_slice(&key_index.to_be_bytes());
+    }
+    bytes
+}
+
+/// The number of `(key_index, value_index)` pairs that fit in one leaf of
+/// `block_size` bytes (a 12-byte `TreeEntry` header plus an 8-byte
+/// `TreeEntryIndices` per entry), capped at what the `count` field (a `u16`)
+/// can represent.
+fn max_leaf_entries(block_size: u32) -> usize {
+    let by_block_size = (block_size as usize).saturating_sub(12) / 8;
+    by_block_size.clamp(1, u16::MAX as usize)
+}
+
+/// Serializes `entries` (already sorted by key) as a chain of leaf blocks,
+/// splitting it into as many leaves of at most `max_leaf_entries(block_size)`
+/// entries as needed and linking their `forward`/`backward` fields once the
+/// whole chain has been allocated, so a tree with more entries than fit in
+/// one leaf (e.g. a huge asset catalog) round-trips instead of silently
+/// truncating. Returns the pointer index of the first leaf.
+fn write_leaf_chain(
+    buffer: &mut Vec<u8>,
+    pointers: &mut Vec<Pointer>,
+    free_pointers: &mut Vec<Pointer>,
+    entries: &[(Vec<u8>, Vec<u8>)],
+    block_size: u32,
+) -> u32 {
+    let max_per_leaf = max_leaf_entries(block_size);
+    let chunks: Vec<&[(Vec<u8>, Vec<u8>)]> =
+        if entries.is_empty() { vec![&entries[..]] } else { entries.chunks(max_per_leaf).collect() };
+
+    let mut leaf_indices = Vec::with_capacity(chunks.len());
+    for chunk in &chunks {
+        let mut indices = Vec::with_capacity(chunk.len());
+        for (key, value) in *chunk {
+            let key_index = alloc(buffer, pointers, free_pointers, key);
+            let value_index = alloc(buffer, pointers, free_pointers, value);
+            indices.push((value_index, key_index));
+        }
+        leaf_indices.push(alloc(buffer, pointers, free_pointers, &leaf_bytes(&indices)));
+    }
+
+    for (i, &leaf_index) in leaf_indices.iter().enumerate() {
+        let address = pointers[leaf_index as usize].address as usize;
+        let forward = leaf_indices.get(i + 1).copied().unwrap_or(0);
+        let backward = if i == 0 { 0 } else { leaf_indices[i - 1] };
+        put_u32_be(buffer, address + 4, forward);
+        put_u32_be(buffer, address + 8, backward);
+    }
+
+    leaf_indices[0]
+}
+
+/// Walks every block reachable from `first_node` — an interior node's chain
+/// of `child` pointers down to the first real leaf, then that leaf chain's
+/// `forward` links — collecting each block along the way: interior blocks,
+/// leaf blocks, and every entry's key/value blocks. The caller can free all
+/// of it at once instead of just `first_node` itself.
+///
+/// A tree's `child` can point directly at a leaf, or (once it's grown past
+/// what the real format's interior-node quirks produce) at an interior node
+/// whose own `child` is stored as four raw bytes just past its block rather
+/// than a registered `Pointer` — see [`crate::cache::NodeReader`]. Using
+/// `NodeReader` here instead of re-parsing `TreeEntry`s by hand means both
+/// shapes are freed correctly. Stops at the first unreadable or
+/// already-visited block, so a corrupt or cyclic chain can't be used to loop
+/// forever.
+fn chain_pointers_to_free<B: AsRef<[u8]>>(bom: &Bom<B>, first_node: u32) -> Vec<Pointer> {
+    let mut freed = Vec::new();
+    let mut seen = HashSet::new();
+    let mut node_index = first_node;
+
+    let leaf_index = loop {
+        if !seen.insert(node_index) {
+            return freed;
+        }
+        let node = match bom.node_reader.get(bom, node_index) {
+            Ok(node) => node,
+            Err(_) => return freed,
+        };
+        if node.is_leaf {
+            break node_index;
+        }
+        if let Some(pointer) = bom.pointer(node_index) {
+            freed.push(Pointer { address: pointer.address, length: pointer.length });
+        }
+        node_index = match node.child {
+            Some(child) => child,
+            None => return freed,
+        };
+    };
+
+    let mut leaf_index = leaf_index;
+    loop {
+        let pointer = match bom.pointer(leaf_index) {
+            Some(pointer) => pointer,
+            None => break,
+        };
+        let node = match bom.node_reader.get(bom, leaf_index) {
+            Ok(node) => node,
+            Err(_) => break,
+        };
+        let bin = Binary::new(bom.buffer.as_ref());
+        for i in 0..node.count {
+            let offset = pointer.address as usize + 12 + i as usize * 8;
+            let indices = match bin.get_buffer(offset, 8).ok().and_then(|buf| TreeEntryIndices::try_from(buf).ok()) {
+                Some(indices) => indices,
+                None => continue,
+            };
+            if let Some(key_ptr) = bom.pointer(indices.key_index) {
+                freed.push(Pointer { address: key_ptr.address, length: key_ptr.length });
+            }
+            if let Some(value_ptr) = bom.pointer(indices.value_index) {
+                freed.push(Pointer { address: value_ptr.address, length: value_ptr.length });
+            }
+        }
+        freed.push(Pointer { address: pointer.address, length: pointer.length });
+
+        if node.forward == 0 || !seen.insert(node.forward) {
+            break;
+        }
+        leaf_index = node.forward;
+    }
+
+    freed
+}
+
+impl Bom<Vec<u8>> {
+    /// Inserts `value` for `key` in `var`'s tree, replacing any existing
+    /// value for that key.
+    ///
+    /// Every entry of `var`'s tree is written out again as a new leaf chain:
+    /// the old chain's blocks are freed first, and each new block is written
+    /// into a freed block that's large enough or appended to the buffer.
+    /// The tree's `child`/`path_count` fields and the header are patched in
+    /// place, and a new index is appended.
+    pub fn set(&mut self, var: &str, key: &[u8], value: &[u8]) -> Result<(), BomError> {
+        let mut entries = self.collect_entries(var)?;
+        match entries.binary_search_by(|(k, _)| k.as_slice().cmp(key)) {
+            Ok(i) => entries[i].1 = value.to_vec(),
+            Err(i) => entries.insert(i, (key.to_vec(), value.to_vec())),
+        }
+        self.write_leaf(var, &entries)
+    }
+
+    /// Removes `key` from `var`'s tree, returning whether it was present.
+    pub fn remove(&mut self, var: &str, key: &[u8]) -> Result<bool, BomError> {
+        let mut entries = self.collect_entries(var)?;
+        match entries.binary_search_by(|(k, _)| k.as_slice().cmp(key)) {
+            Ok(i) => {
+                entries.remove(i);
+                self.write_leaf(var, &entries)?;
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Serializes the current buffer as-is; every mutating method keeps it
+    /// in a valid, directly-writable state.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.buffer)
+    }
+
+    fn collect_entries(&self, var: &str) -> Result<Entries, BomError> {
+        self.reduce_tree_for_variable(var, Vec::new(), |mut acc, key, value| {
+            acc.push((key.to_vec(), value.to_vec()));
+            acc
+        })
+    }
+
+    fn write_leaf(&mut self, var: &str, entries: &[(Vec<u8>, Vec<u8>)]) -> Result<(), BomError> {
+        let var_address = self.pointer_for_var(var).ok_or_else(|| BomError::VariableNotFound(var.to_string()))?.address as usize;
+        let old_tree = Tree::try_from(&self.buffer[var_address..])?;
+
+        // The whole old chain (any interior nodes routing to the real leaf
+        // chain, every leaf in it, and the key/value blocks its entries
+        // point at) is about to become unreachable once `child` is
+        // repointed below; free all of it, not just the first block, so
+        // repeated `set`/`remove` calls reclaim space instead of growing the
+        // buffer without bound.
+        let freed = chain_pointers_to_free(self, old_tree.child);
+        self.free_pointers.extend(freed);
+
+        // A block size too small to hold even one entry isn't worth honoring.
+        let block_size = if old_tree.block_size >= 12 + 8 { old_tree.block_size } else { DEFAULT_BLOCK_SIZE };
+        let leaf_index =
+            write_leaf_chain(&mut self.buffer, &mut self.pointers, &mut self.free_pointers, entries, block_size);
+
+        put_u32_be(&mut self.buffer, var_address + 8, leaf_index); // Tree::child
+        put_u32_be(&mut self.buffer, var_address + 16, entries.len() as u32); // Tree::path_count
+
+        self.rewrite_index();
+        Ok(())
+    }
+
+    fn rewrite_index(&mut self) {
+        // The index we're about to write makes the previous one dead weight;
+        // free it like any other stale block instead of leaving it to rot in
+        // the middle of the buffer.
+        if self.header.index_length > 0 {
+            self.free_pointers.push(Pointer { address: self.header.index_offset, length: self.header.index_length });
+        }
+
+        let mut index_bytes = Vec::new();
+        index_bytes.extend_from_slice(&(self.pointers.len() as u32).to_be_bytes());
+        for pointer in &self.pointers {
+            index_bytes.extend_from_slice(&pointer.address.to_be_bytes());
+            index_bytes.extend_from_slice(&pointer.length.to_be_bytes());
+        }
+        index_bytes.extend_from_slice(&(self.free_pointers.len() as u32).to_be_bytes());
+        for pointer in &self.free_pointers {
+            index_bytes.extend_from_slice(&pointer.address.to_be_bytes());
+            index_bytes.extend_from_slice(&pointer.length.to_be_bytes());
+        }
+
+        let index_offset = self.buffer.len() as u32;
+        let index_length = index_bytes.len() as u32;
+        self.buffer.extend_from_slice(&index_bytes);
+
+        self.header.number_of_blocks = self.pointers.len() as u32;
+        self.header.index_offset = index_offset;
+        self.header.index_length = index_length;
+
+        put_u32_be(&mut self.buffer, 12, self.header.number_of_blocks);
+        put_u32_be(&mut self.buffer, 16, index_offset);
+        put_u32_be(&mut self.buffer, 20, index_length);
+
+        // The leaf we just rewrote (and the child pointer the tree now uses)
+        // may reuse an address we'd previously cached a decoded node for.
+        self.node_reader.invalidate();
+    }
+}
+
+/// Builds a fresh, valid BOM file from scratch: a `BOMStore` header, one
+/// tree per named variable (entries sorted by key, in a chain of as many
+/// leaves as they need), and the variables table.
+#[derive(Default)]
+pub struct BomBuilder {
+    trees: HashMap<String, Entries>,
+}
+
+impl BomBuilder {
+    pub fn new() -> Self {
+        BomBuilder { trees: HashMap::new() }
+    }
+
+    /// Stages `(key, value)` for insertion into `var`'s tree once [`BomBuilder::build`] runs.
+    pub fn insert(mut self, var: &str, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> Self {
+        self.trees.entry(var.to_string()).or_default().push((key.into(), value.into()));
+        self
+    }
+
+    /// Serializes every staged variable into a new BOM file and parses it
+    /// back into a [`Bom`].
+    pub fn build(self) -> Result<Bom<Vec<u8>>, BomError> {
+        let mut buffer = vec![0u8; 32];
+        // Pointer index 0 is conventionally a reserved null block; real BOM
+        // readers (e.g. Apple's `bomutils`) expect it, so seed it before any
+        // real data is allocated rather than handing index 0 to the first
+        // key block.
+        let mut pointers: Vec<Pointer> = vec![Pointer { address: 0, length: 0 }];
+        let mut free_pointers: Vec<Pointer> = Vec::new();
+        let mut vars: Vec<(String, u32)> = Vec::new();
+
+        let mut names: Vec<&String> = self.trees.keys().collect();
+        names.sort();
+
+        for name in names {
+            let mut entries = self.trees[name].clone();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            let leaf_index =
+                write_leaf_chain(&mut buffer, &mut pointers, &mut free_pointers, &entries, DEFAULT_BLOCK_SIZE);
+
+            let mut tree_bytes = Vec::with_capacity(21);
+            tree_bytes.extend_from_slice(b"tree");
+            tree_bytes.extend_from_slice(&1u32.to_be_bytes()); // version
+            tree_bytes.extend_from_slice(&leaf_index.to_be_bytes()); // child
+            tree_bytes.extend_from_slice(&DEFAULT_BLOCK_SIZE.to_be_bytes()); // block_size
+            tree_bytes.extend_from_slice(&(entries.len() as u32).to_be_bytes()); // path_count
+            tree_bytes.push(0); // unknown
+            let tree_index = alloc(&mut buffer, &mut pointers, &mut free_pointers, &tree_bytes);
+
+            vars.push((name.clone(), tree_index));
+        }
+
+        let index_offset = buffer.len() as u32;
+        let mut index_bytes = Vec::new();
+        index_bytes.extend_from_slice(&(pointers.len() as u32).to_be_bytes());
+        for pointer in &pointers {
+            index_bytes.extend_from_slice(&pointer.address.to_be_bytes());
+            index_bytes.extend_from_slice(&pointer.length.to_be_bytes());
+        }
+        index_bytes.extend_from_slice(&0u32.to_be_bytes()); // no free blocks in a freshly built file
+        let index_length = index_bytes.len() as u32;
+        buffer.extend_from_slice(&index_bytes);
+
+        let vars_offset = buffer.len() as u32;
+        let mut vars_bytes = Vec::new();
+        vars_bytes.extend_from_slice(&(vars.len() as u32).to_be_bytes());
+        for (name, tree_index) in &vars {
+            vars_bytes.extend_from_slice(&tree_index.to_be_bytes());
+            vars_bytes.push(name.len() as u8);
+            vars_bytes.extend_from_slice(name.as_bytes());
+        }
+        let vars_length = vars_bytes.len() as u32;
+        buffer.extend_from_slice(&vars_bytes);
+
+        buffer[0..8].copy_from_slice(b"BOMStore");
+        put_u32_be(&mut buffer, 8, 1); // version
+        put_u32_be(&mut buffer, 12, pointers.len() as u32); // number_of_blocks
+        put_u32_be(&mut buffer, 16, index_offset);
+        put_u32_be(&mut buffer, 20, index_length);
+        put_u32_be(&mut buffer, 24, vars_offset);
+        put_u32_be(&mut buffer, 28, vars_length);
+
+        Bom::new(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_remove_round_trip() {
+        let mut bom = BomBuilder::new()
+            .insert("Data", b"a".to_vec(), b"1".to_vec())
+            .insert("Data", b"c".to_vec(), b"3".to_vec())
+            .build()
+            .unwrap();
+
+        bom.set("Data", b"b", b"2").unwrap();
+        assert_eq!(
+            bom.collect_entries("Data").unwrap(),
+            vec![(b"a".to_vec(), b"1".to_vec()), (b"b".to_vec(), b"2".to_vec()), (b"c".to_vec(), b"3".to_vec())]
+        );
+
+        assert!(bom.remove("Data", b"b").unwrap());
+        assert_eq!(bom.collect_entries("Data").unwrap(), vec![(b"a".to_vec(), b"1".to_vec()), (b"c".to_vec(), b"3".to_vec())]);
+        assert!(!bom.remove("Data", b"missing").unwrap());
+
+        let mut out = Vec::new();
+        bom.write_to(&mut out).unwrap();
+        let reloaded = Bom::new(out).unwrap();
+        assert_eq!(reloaded.collect_entries("Data").unwrap(), vec![(b"a".to_vec(), b"1".to_vec()), (b"c".to_vec(), b"3".to_vec())]);
+    }
+
+    /// Hand-builds a tree whose `child` points at an interior node (rather
+    /// than directly at a leaf, as `write_leaf_chain` always produces) to
+    /// prove `set` frees the whole chain behind it, not just that interior
+    /// block.
+    #[test]
+    fn set_frees_entire_chain_through_interior_root() {
+        let mut buffer = vec![0u8; 32];
+        let mut pointers: Vec<Pointer> = vec![Pointer { address: 0, length: 0 }];
+        let mut free_pointers: Vec<Pointer> = Vec::new();
+
+        let key_index = alloc(&mut buffer, &mut pointers, &mut free_pointers, b"a");
+        let value_index = alloc(&mut buffer, &mut pointers, &mut free_pointers, b"1");
+        let leaf_index = alloc(&mut buffer, &mut pointers, &mut free_pointers, &leaf_bytes(&[(value_index, key_index)]));
+
+        // An interior node's child pointer index is stored as four raw bytes
+        // right after its own 12-byte block, not as its own `Pointer` — see
+        // `cache::NodeReader::get`.
+        let interior_address = buffer.len() as u32;
+        buffer.extend_from_slice(&0u16.to_be_bytes()); // is_leaf
+        buffer.extend_from_slice(&0u16.to_be_bytes()); // count
+        buffer.extend_from_slice(&0u32.to_be_bytes()); // forward
+        buffer.extend_from_slice(&0u32.to_be_bytes()); // backward
+        buffer.extend_from_slice(&leaf_index.to_be_bytes()); // child
+        pointers.push(Pointer { address: interior_address, length: 12 });
+        let interior_index = (pointers.len() - 1) as u32;
+
+        let mut tree_bytes = Vec::with_capacity(21);
+        tree_bytes.extend_from_slice(b"tree");
+        tree_bytes.extend_from_slice(&1u32.to_be_bytes()); // version
+        tree_bytes.extend_from_slice(&interior_index.to_be_bytes()); // child
+        tree_bytes.extend_from_slice(&DEFAULT_BLOCK_SIZE.to_be_bytes());
+        tree_bytes.extend_from_slice(&1u32.to_be_bytes()); // path_count
+        tree_bytes.push(0); // unknown
+        let tree_index = alloc(&mut buffer, &mut pointers, &mut free_pointers, &tree_bytes);
+
+        let index_offset = buffer.len() as u32;
+        let mut index_bytes = Vec::new();
+        index_bytes.extend_from_slice(&(pointers.len() as u32).to_be_bytes());
+        for pointer in &pointers {
+            index_bytes.extend_from_slice(&pointer.address.to_be_bytes());
+            index_bytes.extend_from_slice(&pointer.length.to_be_bytes());
+        }
+        index_bytes.extend_from_slice(&0u32.to_be_bytes());
+        let index_length = index_bytes.len() as u32;
+        buffer.extend_from_slice(&index_bytes);
+
+        let vars_offset = buffer.len() as u32;
+        let mut vars_bytes = Vec::new();
+        vars_bytes.extend_from_slice(&1u32.to_be_bytes());
+        vars_bytes.extend_from_slice(&tree_index.to_be_bytes());
+        vars_bytes.push(b"Data".len() as u8);
+        vars_bytes.extend_from_slice(b"Data");
+        let vars_length = vars_bytes.len() as u32;
+        buffer.extend_from_slice(&vars_bytes);
+
+        buffer[0..8].copy_from_slice(b"BOMStore");
+        put_u32_be(&mut buffer, 8, 1);
+        put_u32_be(&mut buffer, 12, pointers.len() as u32);
+        put_u32_be(&mut buffer, 16, index_offset);
+        put_u32_be(&mut buffer, 20, index_length);
+        put_u32_be(&mut buffer, 24, vars_offset);
+        put_u32_be(&mut buffer, 28, vars_length);
+
+        let mut bom = Bom::new(buffer).unwrap();
+        assert_eq!(bom.collect_entries("Data").unwrap(), vec![(b"a".to_vec(), b"1".to_vec())]);
+
+        let old_addresses: HashSet<u32> =
+            [interior_index, key_index, value_index, leaf_index].iter().map(|&index| pointers[index as usize].address).collect();
+
+        bom.set("Data", b"b", b"2").unwrap();
+
+        // The interior node, the leaf it routed to, and that leaf's key and
+        // value blocks should all have been freed — not just the interior
+        // block the old leaf-only walk stopped at. `alloc` hands them out
+        // again straight away, so the four one-byte key/value blocks of the
+        // rewritten tree land exactly in those four freed blocks.
+        let reused: HashSet<u32> =
+            bom.pointers[pointers.len()..pointers.len() + 4].iter().map(|pointer| pointer.address).collect();
+        assert_eq!(
+            reused, old_addresses,
+            "expected interior + leaf + key + value blocks to be freed and reused, got {:?}",
+            bom.pointers
+        );
+        assert_eq!(
+            bom.collect_entries("Data").unwrap(),
+            vec![(b"a".to_vec(), b"1".to_vec()), (b"b".to_vec(), b"2".to_vec())]
+        );
+    }
+}